@@ -3,14 +3,15 @@ use crate::csv_core::utils::*;
 use crate::csv_core::{buffer::*, parser::*};
 use crate::PhysicalIoExpr;
 use crate::ScanAggregation;
-use csv::ByteRecordsIntoIter;
 use polars_arrow::array::*;
 use polars_core::utils::accumulate_dataframes_vertical;
 use polars_core::{prelude::*, POOL};
 use rayon::prelude::*;
 use rayon::ThreadPoolBuilder;
 use std::fmt;
+use std::fs::File;
 use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::Ordering;
 use std::sync::{atomic::AtomicUsize, Arc};
 
@@ -20,8 +21,14 @@ pub struct SequentialReader<R: Read> {
     schema: SchemaRef,
     /// Optional projection for which columns to load (zero-based column indices)
     projection: Option<Vec<usize>>,
-    /// File reader
-    record_iter: Option<ByteRecordsIntoIter<R>>,
+    /// The raw `Read` implementor this reader was constructed from, when reading from a
+    /// stream rather than a `path`. Consumed in full into `bytes` the first time it's
+    /// parsed; absent thereafter.
+    reader: Option<R>,
+    /// Byte buffer the `reader` branch reads the whole input into. Since `reader` is
+    /// consumed by the first read, the buffer is filled once; it stays on the struct
+    /// afterwards.
+    bytes: Vec<u8>,
     /// Batch size (number of records to load each time)
     batch_size: usize,
     /// Current line number, used in error reporting
@@ -36,6 +43,13 @@ pub struct SequentialReader<R: Read> {
     delimiter: u8,
     sample_size: usize,
     chunk_size: usize,
+    /// Override for the initial per-column string-byte capacity guess `parse_csv` starts
+    /// from, in place of the `chunk_size * 100` default. Set via
+    /// [`SequentialReader::seed_str_capacities`].
+    seeded_str_capacities: Option<Vec<usize>>,
+    /// Per-column string-byte capacities `parse_csv` converged on during the last read,
+    /// if any. Taken via [`SequentialReader::take_str_capacities`].
+    observed_str_capacities: Option<Vec<usize>>,
 }
 
 impl<R> fmt::Debug for SequentialReader<R>
@@ -89,13 +103,11 @@ impl<R: Read + Sync + Send> SequentialReader<R> {
         sample_size: usize,
         chunk_size: usize,
     ) -> Self {
-        let csv_reader = init_csv_reader(reader, has_header, delimiter);
-        let record_iter = Some(csv_reader.into_byte_records());
-
         Self {
             schema,
             projection,
-            record_iter,
+            reader: Some(reader),
+            bytes: Vec::new(),
             batch_size,
             line_number: if has_header { 1 } else { 0 },
             ignore_parser_errors,
@@ -108,9 +120,26 @@ impl<R: Read + Sync + Send> SequentialReader<R> {
             delimiter,
             sample_size,
             chunk_size,
+            seeded_str_capacities: None,
+            observed_str_capacities: None,
         }
     }
 
+    /// Seed the initial per-column string-byte capacity guess `parse_csv` starts from,
+    /// overriding the `chunk_size * 100` default. Used by [`scan_csv_dataset`] to carry
+    /// the capacity a previous file's read converged on into the next file's reader
+    /// instead of starting cold every time.
+    pub(crate) fn seed_str_capacities(&mut self, capacities: Vec<usize>) {
+        self.seeded_str_capacities = Some(capacities);
+    }
+
+    /// Take the per-column string-byte capacities `parse_csv` converged on during the
+    /// last read, if any, to pass into [`SequentialReader::seed_str_capacities`] on the
+    /// next reader.
+    pub(crate) fn take_str_capacities(&mut self) -> Option<Vec<usize>> {
+        self.observed_str_capacities.take()
+    }
+
     fn find_starting_point<'a>(&self, mut bytes: &'a [u8]) -> Result<&'a [u8]> {
         // Skip all leading white space and the occasional utf8-bom
         bytes = skip_line_ending(skip_whitespace(skip_bom(bytes)).0).0;
@@ -208,10 +237,15 @@ impl<R: Read + Sync + Send> SequentialReader<R> {
             .filter(|i| self.schema.field(*i).unwrap().data_type() == &DataType::Utf8)
             .collect();
         let init_str_bytes = chunk_size * 100;
-        let str_capacities: Vec<_> = str_columns
-            .iter()
-            .map(|_| AtomicUsize::new(init_str_bytes))
-            .collect();
+        let str_capacities: Vec<_> = match self.seeded_str_capacities.take() {
+            Some(seeded) if seeded.len() == str_columns.len() => {
+                seeded.into_iter().map(AtomicUsize::new).collect()
+            }
+            _ => str_columns
+                .iter()
+                .map(|_| AtomicUsize::new(init_str_bytes))
+                .collect(),
+        };
 
         // split the file by the nearest new line characters such that every thread processes
         // approximately the same number of rows.
@@ -301,6 +335,13 @@ impl<R: Read + Sync + Send> SequentialReader<R> {
                 .collect::<Result<Vec<_>>>()
         })?;
 
+        self.observed_str_capacities = Some(
+            str_capacities
+                .iter()
+                .map(|c| c.load(Ordering::SeqCst))
+                .collect(),
+        );
+
         accumulate_dataframes_vertical(dfs)
     }
 
@@ -312,7 +353,7 @@ impl<R: Read + Sync + Send> SequentialReader<R> {
     ) -> Result<DataFrame> {
         let n_threads = self.n_threads.unwrap_or_else(num_cpus::get);
 
-        let mut df = match (&self.path, self.record_iter.is_some()) {
+        let mut df = match (&self.path, self.reader.is_some()) {
             (Some(p), _) => {
                 let file = std::fs::File::open(p).unwrap();
                 let mmap = unsafe { memmap::Mmap::map(&file).unwrap() };
@@ -320,15 +361,19 @@ impl<R: Read + Sync + Send> SequentialReader<R> {
                 self.parse_csv(n_threads, bytes, predicate.as_ref())?
             }
             (None, true) => {
-                let mut r = std::mem::take(&mut self.record_iter).unwrap().into_reader();
-                let mut bytes = Vec::with_capacity(1024 * 128);
-                r.get_mut().read_to_end(&mut bytes)?;
-                if !bytes.is_empty()
-                    && (bytes[bytes.len() - 1] != b'\n' || bytes[bytes.len() - 1] != b'\r')
+                let mut r = std::mem::take(&mut self.reader).unwrap();
+                self.bytes.clear();
+                r.read_to_end(&mut self.bytes)?;
+                if !self.bytes.is_empty()
+                    && (self.bytes[self.bytes.len() - 1] != b'\n'
+                        || self.bytes[self.bytes.len() - 1] != b'\r')
                 {
-                    bytes.push(b'\n')
+                    self.bytes.push(b'\n')
                 }
-                self.parse_csv(n_threads, &bytes, predicate.as_ref())?
+                let bytes = std::mem::take(&mut self.bytes);
+                let result = self.parse_csv(n_threads, &bytes, predicate.as_ref());
+                self.bytes = bytes;
+                result?
             }
             _ => return Err(PolarsError::Other("file or reader must be set".into())),
         };
@@ -350,6 +395,428 @@ impl<R: Read + Sync + Send> SequentialReader<R> {
         }
         Ok(df)
     }
+
+    /// Turn this reader into an iterator that yields `DataFrame`s of at most `batch_size`
+    /// rows, in file order, bounded by `n_rows`.
+    ///
+    /// This lets downstream consumers (lazy physical plans, `ScanAggregation`) process and
+    /// drop batches incrementally instead of materializing the whole file as a single
+    /// `DataFrame` up front.
+    pub fn into_batched_iter(mut self) -> Result<BatchedCsvReader<R>> {
+        let bytes = match (&self.path, self.reader.is_some()) {
+            (Some(p), _) => {
+                let file = std::fs::File::open(p).unwrap();
+                let mmap = unsafe { memmap::Mmap::map(&file).unwrap() };
+                ByteSource::Mmap(mmap)
+            }
+            (None, true) => {
+                let mut r = std::mem::take(&mut self.reader).unwrap();
+                let mut bytes = std::mem::take(&mut self.bytes);
+                bytes.clear();
+                r.read_to_end(&mut bytes)?;
+                if !bytes.is_empty()
+                    && (bytes[bytes.len() - 1] != b'\n' && bytes[bytes.len() - 1] != b'\r')
+                {
+                    bytes.push(b'\n')
+                }
+                ByteSource::Owned(bytes)
+            }
+            _ => return Err(PolarsError::Other("file or reader must be set".into())),
+        };
+
+        let start_offset = bytes.len() - self.find_starting_point(bytes.as_slice())?.len();
+
+        let projection = self
+            .projection
+            .take()
+            .map(|mut v| {
+                v.sort_unstable();
+                v
+            })
+            .unwrap_or_else(|| (0..self.schema.fields().len()).collect());
+
+        let pool = BufferPool::new(
+            projection.clone(),
+            self.schema.clone(),
+            self.delimiter,
+            self.batch_size,
+        )?;
+
+        Ok(BatchedCsvReader {
+            schema: self.schema.clone(),
+            projection,
+            bytes,
+            position: start_offset,
+            delimiter: self.delimiter,
+            ignore_parser_errors: self.ignore_parser_errors,
+            encoding: self.encoding,
+            batch_size: self.batch_size,
+            n_rows: self.n_rows,
+            rows_read: 0,
+            pool,
+            finished: false,
+            phantom: std::marker::PhantomData,
+        })
+    }
+}
+
+/// The raw bytes a [`BatchedCsvReader`] slices batches out of: either a memory-mapped file
+/// (lazily paged in by the OS as batches touch new pages) or an owned buffer read from a
+/// non-seekable `reader`. Keeping the `path` branch as an `Mmap` rather than copying it into
+/// a `Vec` is what actually bounds peak memory to the in-flight batches rather than the
+/// whole file.
+enum ByteSource {
+    Mmap(memmap::Mmap),
+    Owned(Vec<u8>),
+}
+
+impl ByteSource {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            ByteSource::Mmap(m) => &m[..],
+            ByteSource::Owned(v) => v.as_slice(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+}
+
+/// Iterator returned by [`SequentialReader::into_batched_iter`]. Yields successive
+/// `DataFrame`s of at most `batch_size` rows, reusing the manual `parse_lines` path one
+/// batch at a time rather than parsing and concatenating the whole file up front.
+pub struct BatchedCsvReader<R: Read> {
+    schema: SchemaRef,
+    projection: Vec<usize>,
+    bytes: ByteSource,
+    position: usize,
+    delimiter: u8,
+    ignore_parser_errors: bool,
+    encoding: CsvEncoding,
+    batch_size: usize,
+    n_rows: Option<usize>,
+    rows_read: usize,
+    pool: BufferPool,
+    finished: bool,
+    phantom: std::marker::PhantomData<R>,
+}
+
+impl<R: Read> Iterator for BatchedCsvReader<R> {
+    type Item = Result<DataFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished || self.position >= self.bytes.len() {
+            return None;
+        }
+
+        let mut rows_wanted = self.batch_size;
+        if let Some(n_rows) = self.n_rows {
+            let remaining = n_rows.saturating_sub(self.rows_read);
+            if remaining == 0 {
+                self.finished = true;
+                return None;
+            }
+            rows_wanted = rows_wanted.min(remaining);
+        }
+
+        let local_bytes = &self.bytes.as_slice()[self.position..];
+        let mut offset = 0;
+        for _ in 0..rows_wanted {
+            match next_line_position(
+                &local_bytes[offset..],
+                self.schema.fields().len(),
+                self.delimiter,
+            ) {
+                Some(pos) => offset += pos,
+                None => {
+                    offset = local_bytes.len();
+                    break;
+                }
+            }
+        }
+
+        let chunk = &local_bytes[..offset];
+        self.position += offset;
+        if chunk.is_empty() {
+            self.finished = true;
+            return None;
+        }
+
+        let result = (|| {
+            let mut buffers = self.pool.take();
+            parse_lines(
+                chunk,
+                0,
+                self.delimiter,
+                &self.projection,
+                &mut buffers,
+                self.ignore_parser_errors,
+                self.encoding,
+            )?;
+            Ok(DataFrame::new_no_checks(
+                buffers.into_iter().map(|buf| buf.into_series()).collect(),
+            ))
+        })();
+
+        match result {
+            Ok(df) => {
+                // Feed this batch's actual string-byte footprint back so the next batch's
+                // `pool.take()` is pre-sized off it instead of the fixed initial guess --
+                // the same statistic `parse_csv` keeps per chunk, just carried across
+                // batches instead of starting cold on every one.
+                self.pool.record_df(&df);
+                self.rows_read += df.height();
+                if self.position >= self.bytes.len() {
+                    self.finished = true;
+                }
+                Some(Ok(df))
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Scan the complete (i.e. newline-terminated, quote-balanced, and matching `n_fields`
+/// unquoted `delimiter`-separated fields) records at the start of `bytes`, the same way
+/// `next_line_position` validates a boundary by field count, but quote-aware so a newline
+/// embedded in a quoted field is never mistaken for a record boundary or miscounted as a
+/// delimiter.
+///
+/// Returns `(end_offset, n_records)`: the byte offset just past the last validated record,
+/// and how many records were found before it. `(0, 0)` if `bytes` contains no full,
+/// well-formed record yet.
+fn scan_complete_records(bytes: &[u8], n_fields: usize, delimiter: u8) -> (usize, usize) {
+    let mut in_quotes = false;
+    let mut fields_in_line = 1;
+    let mut end_offset = 0;
+    let mut n_records = 0;
+
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'"' => in_quotes = !in_quotes,
+            b if !in_quotes && b == delimiter => fields_in_line += 1,
+            b'\n' if !in_quotes => {
+                if fields_in_line == n_fields {
+                    end_offset = i + 1;
+                    n_records += 1;
+                }
+                fields_in_line = 1;
+            }
+            _ => {}
+        }
+    }
+    (end_offset, n_records)
+}
+
+/// A push-based, incremental CSV decoder.
+///
+/// Unlike [`SequentialReader`], which requires the entire input to be available up front
+/// (either memory-mapped or read fully into memory), a `CsvDecoder` can be fed arbitrary
+/// byte chunks as they arrive from a non-seekable source such as stdin, a socket, or a
+/// streaming decompressor. Call [`CsvDecoder::decode`] for every chunk of input, periodically
+/// call [`CsvDecoder::flush`] to materialize the rows parsed so far into a `DataFrame`, and
+/// call [`CsvDecoder::finish`] once at EOF to flush any trailing partial record.
+pub struct CsvDecoder {
+    /// Sorted, zero-based column indices to materialize.
+    projection: Vec<usize>,
+    /// Number of fields a well-formed record of the source schema has, used to validate
+    /// candidate record boundaries the same way `next_line_position` validates them for
+    /// the batch path.
+    n_fields: usize,
+    pool: BufferPool,
+    buffers: Vec<Buffer>,
+    /// Bytes that have been appended via `decode` but not yet parsed because they
+    /// belong to an incomplete trailing record.
+    accumulator: Vec<u8>,
+    delimiter: u8,
+    ignore_parser_errors: bool,
+    encoding: CsvEncoding,
+    batch_size: usize,
+    n_rows: Option<usize>,
+    /// Records parsed into `buffers` since the last `flush`, not yet returned as a `DataFrame`.
+    rows_buffered: usize,
+    /// Rows already handed back to the caller across every `flush`/`finish` call.
+    rows_parsed: usize,
+    /// Rows drained from `buffers` by a `flush` call that exceeded `batch_size` and are
+    /// waiting for a follow-up `flush` call rather than being dropped.
+    pending: Option<DataFrame>,
+}
+
+impl CsvDecoder {
+    /// Create a new decoder for `schema`, projecting `projection` (or all columns if `None`).
+    pub fn new(
+        schema: SchemaRef,
+        projection: Option<Vec<usize>>,
+        delimiter: u8,
+        ignore_parser_errors: bool,
+        encoding: CsvEncoding,
+        batch_size: usize,
+        n_rows: Option<usize>,
+    ) -> Result<Self> {
+        let mut projection =
+            projection.unwrap_or_else(|| (0..schema.fields().len()).collect::<Vec<_>>());
+        projection.sort_unstable();
+        let n_fields = schema.fields().len();
+
+        let pool = BufferPool::new(projection.clone(), schema, delimiter, batch_size)?;
+        let buffers = pool.take();
+
+        Ok(Self {
+            projection,
+            n_fields,
+            pool,
+            buffers,
+            accumulator: Vec::with_capacity(1024 * 64),
+            delimiter,
+            ignore_parser_errors,
+            encoding,
+            batch_size,
+            n_rows,
+            rows_buffered: 0,
+            rows_parsed: 0,
+            pending: None,
+        })
+    }
+
+    /// Rows already queued up across everything parsed so far (returned, buffered, or
+    /// drained-but-pending), used to decide whether `n_rows` has been satisfied.
+    fn rows_queued(&self) -> usize {
+        self.rows_parsed
+            + self.rows_buffered
+            + self.pending.as_ref().map(|df| df.height()).unwrap_or(0)
+    }
+
+    /// `false` once `n_rows` worth of records have already been parsed or queued, meaning
+    /// further input would just be thrown away. Callers feeding a non-seekable stream
+    /// (stdin, a socket) should check this and stop reading once it flips, rather than
+    /// draining the whole stream regardless of how many rows were asked for.
+    pub fn wants_more(&self) -> bool {
+        match self.n_rows {
+            Some(n_rows) => self.rows_queued() < n_rows,
+            None => true,
+        }
+    }
+
+    /// Append `input` to the internal accumulator, parse every complete record it now
+    /// contains into the column buffers, and retain the trailing partial record (if any)
+    /// for the next call. Returns the number of bytes of `input` that were consumed; `0`
+    /// once [`CsvDecoder::wants_more`] is `false`, signalling the caller to stop feeding it.
+    ///
+    /// Quoted fields containing embedded newlines are never split: a boundary is only
+    /// considered complete once quote state has returned to "unquoted" AND the candidate
+    /// line splits into exactly as many delimiter-separated fields as the schema expects.
+    pub fn decode(&mut self, input: &[u8]) -> Result<usize> {
+        if !self.wants_more() {
+            return Ok(0);
+        }
+
+        let consumed = input.len();
+        self.accumulator.extend_from_slice(input);
+
+        let (complete_end, n_records) =
+            scan_complete_records(&self.accumulator, self.n_fields, self.delimiter);
+        if n_records == 0 {
+            return Ok(consumed);
+        }
+
+        let remainder = self.accumulator.split_off(complete_end);
+        let complete = std::mem::replace(&mut self.accumulator, remainder);
+
+        parse_lines(
+            &complete,
+            0,
+            self.delimiter,
+            &self.projection,
+            &mut self.buffers,
+            self.ignore_parser_errors,
+            self.encoding,
+        )?;
+        self.rows_buffered += n_records;
+
+        Ok(consumed)
+    }
+
+    /// Slice at most `batch_size` rows bounded by the remaining `n_rows` budget off the
+    /// front of `df`, stashing anything left over in `self.pending` for the next `flush`
+    /// call instead of returning it all at once.
+    ///
+    /// Once the `n_rows` budget is already exhausted, `wanted` is `0` and the remainder of
+    /// `df` is discarded outright rather than re-queued as `pending` -- re-queuing the
+    /// whole (unchanged) remainder would make `pending` never shrink, so `finish`'s
+    /// drain-while-pending loop would never terminate.
+    fn take_batch(&mut self, df: DataFrame) -> Result<DataFrame> {
+        let mut wanted = self.batch_size.min(df.height());
+        if let Some(n_rows) = self.n_rows {
+            wanted = wanted.min(n_rows.saturating_sub(self.rows_parsed));
+        }
+
+        let head = if wanted >= df.height() {
+            df
+        } else if wanted == 0 {
+            df.slice(0, 0)?
+        } else {
+            self.pending = Some(df.slice(wanted as i64, df.height() - wanted)?);
+            df.slice(0, wanted)?
+        };
+        self.rows_parsed += head.height();
+        Ok(head)
+    }
+
+    /// Drain the current buffers into a `DataFrame`, respecting `batch_size`/`n_rows`: a
+    /// single call returns at most `batch_size` rows (and never more than `n_rows` in
+    /// total across every call), even if several `decode` calls queued up more than that
+    /// without an intervening `flush`. Anything in excess is held in `pending` and handed
+    /// out by the next `flush` call rather than discarded. Also feeds this batch's actual
+    /// string-byte footprint back into the pool so the next set of buffers it hands out
+    /// is pre-sized to what this one converged on instead of the fixed initial guess.
+    pub fn flush(&mut self) -> Result<DataFrame> {
+        if let Some(pending) = self.pending.take() {
+            return self.take_batch(pending);
+        }
+
+        let buffers = std::mem::take(&mut self.buffers);
+        let df =
+            DataFrame::new_no_checks(buffers.into_iter().map(|buf| buf.into_series()).collect());
+
+        self.pool.record_df(&df);
+        self.buffers = self.pool.take();
+        self.rows_buffered = 0;
+
+        self.take_batch(df)
+    }
+
+    /// Flush any trailing record still held in the accumulator at EOF, then drain every
+    /// remaining `flush` in full (ignoring `batch_size` chunking, since there's no more
+    /// input coming) and return it all as one final `DataFrame`.
+    pub fn finish(&mut self) -> Result<DataFrame> {
+        if !self.accumulator.is_empty() {
+            let trailing = std::mem::take(&mut self.accumulator);
+            parse_lines(
+                &trailing,
+                0,
+                self.delimiter,
+                &self.projection,
+                &mut self.buffers,
+                self.ignore_parser_errors,
+                self.encoding,
+            )?;
+            // the trailing record has no terminating newline, so `scan_complete_records`
+            // can't validate it the normal way; there's always exactly one such record.
+            self.rows_buffered += 1;
+        }
+
+        let mut dfs = vec![self.flush()?];
+        // a `flush` that hands out `pending` leaves the buffers undrained, so keep going
+        // until both are empty
+        while self.pending.is_some() || self.rows_buffered > 0 {
+            dfs.push(self.flush()?);
+        }
+        accumulate_dataframes_vertical(dfs)
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -414,3 +881,381 @@ pub fn build_csv_reader<R: 'static + Read + Seek + Sync + Send>(
         chunk_size,
     ))
 }
+
+/// One `key=value` partition component parsed out of a directory name in a Hive-style
+/// layout, e.g. `region=eu` in `region=eu/year=2021/part.csv`.
+#[derive(Debug, Clone)]
+struct HivePartition {
+    name: String,
+    value: String,
+}
+
+/// Parse the `key=value` directory components of `path`, in root-to-leaf order.
+/// Components that don't match the `key=value` shape (including the file name itself)
+/// are ignored.
+fn parse_hive_partitions(path: &Path) -> Vec<HivePartition> {
+    path.parent()
+        .into_iter()
+        .flat_map(|dir| dir.components())
+        .filter_map(|c| c.as_os_str().to_str())
+        .filter_map(|s| {
+            let (name, value) = s.split_once('=')?;
+            Some(HivePartition {
+                name: name.to_string(),
+                value: value.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Infer a dtype for a partition value the same way schema inference would for a CSV
+/// field: try integer, then float, and fall back to a plain string.
+fn infer_partition_dtype(value: &str) -> DataType {
+    if value.parse::<i64>().is_ok() {
+        DataType::Int64
+    } else if value.parse::<f64>().is_ok() {
+        DataType::Float64
+    } else {
+        DataType::Utf8
+    }
+}
+
+/// Materialize a file's Hive partitions as constant columns matching the length of `height`.
+fn partition_columns(partitions: &[HivePartition], height: usize) -> Result<Vec<Series>> {
+    partitions
+        .iter()
+        .map(|p| match infer_partition_dtype(&p.value) {
+            DataType::Int64 => {
+                let v: i64 = p.value.parse().unwrap();
+                Ok(Series::new(&p.name, &vec![v; height]))
+            }
+            DataType::Float64 => {
+                let v: f64 = p.value.parse().unwrap();
+                Ok(Series::new(&p.name, &vec![v; height]))
+            }
+            _ => Ok(Series::new(&p.name, &vec![p.value.as_str(); height])),
+        })
+        .collect()
+}
+
+/// A one-row `DataFrame` of just the partition columns of a file, used to evaluate
+/// predicate pushdown without opening the file.
+fn partition_only_df(partitions: &[HivePartition]) -> Result<DataFrame> {
+    DataFrame::new(partition_columns(partitions, 1)?)
+}
+
+/// `true` if `predicate` can be proven false from partition columns alone, meaning the
+/// whole file can be skipped without ever being opened. Any ambiguity (the predicate
+/// touches a non-partition column, or evaluation fails) conservatively returns `false`.
+fn file_pruned_by_partitions(
+    predicate: Option<&Arc<dyn PhysicalIoExpr>>,
+    partitions: &[HivePartition],
+) -> bool {
+    let predicate = match predicate {
+        Some(p) => p,
+        None => return false,
+    };
+    if partitions.is_empty() {
+        return false;
+    }
+    let df = match partition_only_df(partitions) {
+        Ok(df) => df,
+        Err(_) => return false,
+    };
+    match predicate.evaluate(&df) {
+        Ok(s) => match s.bool() {
+            Ok(ca) => matches!(ca.get(0), Some(false)),
+            Err(_) => false,
+        },
+        Err(_) => false,
+    }
+}
+
+/// Merge two inferred per-file schemas into one, widening a field's dtype when files
+/// disagree (e.g. one file's column parsed as `Int64`, another's as `Float64`) and
+/// falling back to `Utf8` when the dtypes can't be reconciled.
+fn widen_schema(acc: &mut Schema, incoming: &Schema) {
+    for field in incoming.fields() {
+        match acc.field_with_name(field.name()) {
+            Ok(existing) if existing.data_type() != field.data_type() => {
+                let widened = match (existing.data_type(), field.data_type()) {
+                    (DataType::Int64, DataType::Float64) | (DataType::Float64, DataType::Int64) => {
+                        DataType::Float64
+                    }
+                    _ => DataType::Utf8,
+                };
+                let name = field.name().clone();
+                acc.with_column(name, widened);
+            }
+            Ok(_) => {}
+            Err(_) => acc.with_column(field.name().clone(), field.data_type().clone()),
+        }
+    }
+}
+
+/// Enumerate the CSV files backing a Hive-partitioned dataset described by `path_or_glob`.
+///
+/// `path_or_glob` may be a single file, a directory (scanned recursively for `*.csv`), or
+/// a glob pattern. Files are returned in a stable, sorted order so that schema inference
+/// and concatenation are deterministic.
+fn expand_csv_dataset_paths(path_or_glob: &str) -> Result<Vec<PathBuf>> {
+    let direct = Path::new(path_or_glob);
+    let mut paths = if direct.is_dir() {
+        glob::glob(&format!("{}/**/*.csv", path_or_glob.trim_end_matches('/')))
+            .map_err(|e| PolarsError::Other(format!("invalid glob pattern: {}", e).into()))?
+            .filter_map(|p| p.ok())
+            .collect::<Vec<_>>()
+    } else {
+        glob::glob(path_or_glob)
+            .map_err(|e| PolarsError::Other(format!("invalid glob pattern: {}", e).into()))?
+            .filter_map(|p| p.ok())
+            .collect::<Vec<_>>()
+    };
+    paths.sort();
+    if paths.is_empty() {
+        return Err(PolarsError::NoData(
+            format!("no csv files found at {}", path_or_glob).into(),
+        ));
+    }
+    Ok(paths)
+}
+
+/// Scan a directory or glob of CSV files as a single logical dataset.
+///
+/// This is the Hive-partitioned counterpart to [`build_csv_reader`]: rather than a single
+/// `path`, it accepts anything [`expand_csv_dataset_paths`] can enumerate, infers one
+/// unified `Schema` across the matching files that are not pruned by `predicate`
+/// (widening dtypes where files disagree), and
+/// concatenates their contents. `key=value` directory components in each file's path
+/// (e.g. `region=eu/year=2021/part.csv`) are parsed out and appended to that file's
+/// `DataFrame` as constant partition columns. When `predicate` only references partition
+/// columns, whole files can be proven irrelevant and are never opened.
+#[allow(clippy::too_many_arguments)]
+pub fn scan_csv_dataset(
+    path_or_glob: &str,
+    delimiter: Option<u8>,
+    has_header: bool,
+    ignore_parser_errors: bool,
+    n_rows: Option<usize>,
+    skip_rows: usize,
+    projection: Option<Vec<usize>>,
+    schema_overwrite: Option<&Schema>,
+    encoding: CsvEncoding,
+    n_threads: Option<usize>,
+    sample_size: usize,
+    chunk_size: usize,
+    batch_size: usize,
+    max_records: Option<usize>,
+    predicate: Option<Arc<dyn PhysicalIoExpr>>,
+) -> Result<DataFrame> {
+    // Prune on partition values before schema inference, so pruned files are never opened.
+    let paths: Vec<PathBuf> = expand_csv_dataset_paths(path_or_glob)?
+        .into_iter()
+        .filter(|p| !file_pruned_by_partitions(predicate.as_ref(), &parse_hive_partitions(p)))
+        .collect();
+    if paths.is_empty() {
+        return Err(PolarsError::NoData(
+            format!("every csv file at {} was pruned by the predicate", path_or_glob).into(),
+        ));
+    }
+    let delimiter = delimiter.unwrap_or(b',');
+
+    let mut schema: Option<Schema> = None;
+    let mut file_schemas = Vec::with_capacity(paths.len());
+    for path in &paths {
+        let mut file = File::open(path)?;
+        let (file_schema, _) =
+            infer_file_schema(&mut file, delimiter, None, has_header, schema_overwrite)?;
+        match &mut schema {
+            Some(acc) => widen_schema(acc, &file_schema),
+            None => schema = Some(file_schema.clone()),
+        }
+        file_schemas.push(file_schema);
+    }
+    let schema = schema.expect("at least one file");
+
+    // `widen_schema` only reconciles dtypes for fields with a shared name; it can't paper
+    // over files that introduce or drop columns entirely. Parsing a narrower file against
+    // the wider unified schema would silently misalign columns (or go out of bounds), so
+    // require every file in the dataset to carry exactly the same set of column names.
+    for (path, file_schema) in paths.iter().zip(file_schemas.iter()) {
+        let same_columns = file_schema.fields().len() == schema.fields().len()
+            && file_schema
+                .fields()
+                .iter()
+                .all(|f| schema.field_with_name(f.name()).is_ok());
+        if !same_columns {
+            return Err(PolarsError::Other(
+                format!(
+                    "schema mismatch scanning hive dataset at {}: file {} has columns {:?}, \
+                    but the dataset's other files share columns {:?}. \
+                    scan_csv_dataset requires every file to have the same columns; \
+                    schema-evolving datasets must be scanned per-schema and aligned by name.",
+                    path_or_glob,
+                    path.display(),
+                    file_schema
+                        .fields()
+                        .iter()
+                        .map(|f| f.name())
+                        .collect::<Vec<_>>(),
+                    schema.fields().iter().map(|f| f.name()).collect::<Vec<_>>(),
+                )
+                .into(),
+            ));
+        }
+    }
+    let schema = Arc::new(schema);
+
+    let mut rows_left = n_rows;
+    let mut dfs = Vec::with_capacity(paths.len());
+    // Carries the string-byte capacity the previous file's reader converged on into the
+    // next file's reader, so a dataset of many files isn't a cold start on every one --
+    // the same statistic `parse_csv` keeps per chunk within a single file, just threaded
+    // across files here too.
+    let mut seeded_str_capacities: Option<Vec<usize>> = None;
+    for path in &paths {
+        if rows_left == Some(0) {
+            break;
+        }
+        let partitions = parse_hive_partitions(path);
+        if file_pruned_by_partitions(predicate.as_ref(), &partitions) {
+            continue;
+        }
+
+        let file = File::open(path)?;
+        let mut reader = build_csv_reader(
+            file,
+            rows_left,
+            skip_rows,
+            projection.clone(),
+            batch_size,
+            max_records,
+            Some(delimiter),
+            has_header,
+            ignore_parser_errors,
+            Some(schema.clone()),
+            None,
+            encoding,
+            n_threads,
+            Some(path.to_string_lossy().into_owned()),
+            schema_overwrite,
+            sample_size,
+            chunk_size,
+        )?;
+        if let Some(capacities) = seeded_str_capacities.take() {
+            reader.seed_str_capacities(capacities);
+        }
+        let mut df = reader.as_df(predicate.clone(), None)?;
+        seeded_str_capacities = reader.take_str_capacities();
+
+        if !partitions.is_empty() {
+            let height = df.height();
+            for s in partition_columns(&partitions, height)? {
+                df.with_column(s)?;
+            }
+        }
+
+        if let Some(n) = rows_left {
+            rows_left = Some(n.saturating_sub(df.height()));
+        }
+        dfs.push(df);
+    }
+
+    accumulate_dataframes_vertical(dfs)
+}
+
+/// Caches the per-column string-byte capacities learned across repeated reads, so a
+/// long-lived reader processing many files or many batches starts each new set of
+/// buffers pre-sized from what the previous one actually needed instead of the fixed
+/// `chunk_size * 100` guess. [`BufferPool::record_df`] feeds the actual footprint of a
+/// finished batch back in, and [`BufferPool::take`] hands out the next set of buffers
+/// built from the resulting capacities.
+///
+/// Buffers themselves are rebuilt fresh on every `take` rather than reused in place: a
+/// `Buffer` is consumed (moved) by `into_series` once it's drained into a `DataFrame`, the
+/// same way `parse_csv`'s own per-chunk loop rebuilds its buffers every chunk while only
+/// the `str_capacities` statistic persists -- there's no backing allocation left over to
+/// hand back afterwards. What `take` eliminates is the repeated *cold start*, not the
+/// per-batch allocation itself.
+pub struct BufferPool {
+    projection: Vec<usize>,
+    schema: SchemaRef,
+    delimiter: u8,
+    local_capacity: usize,
+    str_capacities: Vec<AtomicUsize>,
+}
+
+impl BufferPool {
+    /// Build a pool for `projection` over `schema`, seeding the string-byte capacities
+    /// with a `local_capacity * 100` guess, the same rows-times-100 rule `parse_csv`
+    /// applies to `chunk_size` on a cold start.
+    pub fn new(
+        projection: Vec<usize>,
+        schema: SchemaRef,
+        delimiter: u8,
+        local_capacity: usize,
+    ) -> Result<Self> {
+        let init_str_bytes = local_capacity * 100;
+        let str_capacities: Vec<_> = projection
+            .iter()
+            .copied()
+            .filter(|i| schema.field(*i).unwrap().data_type() == &DataType::Utf8)
+            .map(|_| AtomicUsize::new(init_str_bytes))
+            .collect();
+
+        // Validate eagerly so `take` can assume `init_buffers` will keep succeeding.
+        init_buffers(
+            &projection,
+            local_capacity,
+            &schema,
+            &str_capacities,
+            delimiter,
+        )?;
+
+        Ok(Self {
+            projection,
+            schema,
+            delimiter,
+            local_capacity,
+            str_capacities,
+        })
+    }
+
+    /// Build a fresh set of buffers sized from the currently learned string capacities.
+    /// Call [`BufferPool::record_df`] first so the sizing reflects the batch that was
+    /// just drained rather than a generation-old measurement.
+    pub fn take(&self) -> Vec<Buffer> {
+        init_buffers(
+            &self.projection,
+            self.local_capacity,
+            &self.schema,
+            &self.str_capacities,
+            self.delimiter,
+        )
+        .expect("projection and schema were already validated in BufferPool::new")
+    }
+
+    /// Record the string-byte footprint a just-finished batch needed for the `nth`
+    /// string column in the projection, growing the remembered capacity if it's a new
+    /// high water mark.
+    pub fn update_str_capacity(&self, nth_str_column: usize, bytes_needed: usize) {
+        self.str_capacities[nth_str_column].fetch_max(bytes_needed, Ordering::SeqCst);
+    }
+
+    /// Measure every projected Utf8 column of a just-finished batch and feed its
+    /// string-byte footprint back via [`BufferPool::update_str_capacity`], mirroring the
+    /// running statistic `parse_csv` keeps per chunk. Without this the capacity stays
+    /// pinned at the initial guess forever; call it once per drained `DataFrame` before
+    /// the next [`BufferPool::take`].
+    pub fn record_df(&self, df: &DataFrame) {
+        let mut str_index = 0;
+        // `df` holds only the projected columns, so its column at `pos` is schema field `i`
+        for (pos, &i) in self.projection.iter().enumerate() {
+            if self.schema.field(i).unwrap().data_type() == &DataType::Utf8 {
+                let ca = df.select_at_idx(pos).unwrap().utf8().unwrap();
+                self.update_str_capacity(str_index, ca.get_values_size());
+                str_index += 1;
+            }
+        }
+    }
+}